@@ -0,0 +1,67 @@
+use bytemuck::{Pod, Zeroable};
+use cgmath::{InnerSpace, Matrix4, Point3, Rad, Vector3};
+
+/// wgpu's normalized device coordinates use a `0.0..1.0` depth range, while
+/// `cgmath`'s perspective matrix targets OpenGL's `-1.0..1.0`. This remaps the
+/// z axis by scaling by `0.5` and offsetting by `0.5`.
+#[rustfmt::skip]
+pub const OPENGL_TO_WGPU_MATRIX: Matrix4<f32> = Matrix4::new(
+    1.0, 0.0, 0.0, 0.0,
+    0.0, 1.0, 0.0, 0.0,
+    0.0, 0.0, 0.5, 0.0,
+    0.0, 0.0, 0.5, 1.0,
+);
+
+/// A perspective camera described by its eye/target/up and projection params.
+pub struct Camera {
+    pub eye: Point3<f32>,
+    pub target: Point3<f32>,
+    pub up: Vector3<f32>,
+    pub aspect: f32,
+    pub fovy: f32,
+    pub znear: f32,
+    pub zfar: f32,
+}
+
+impl Camera {
+    pub fn build_view_projection_matrix(&self) -> Matrix4<f32> {
+        let view = Matrix4::look_at_rh(self.eye, self.target, self.up);
+        let proj = cgmath::perspective(Rad(self.fovy), self.aspect, self.znear, self.zfar);
+        OPENGL_TO_WGPU_MATRIX * proj * view
+    }
+
+    /// The normalized direction from the eye toward the target.
+    pub fn forward(&self) -> Vector3<f32> {
+        (self.target - self.eye).normalize()
+    }
+
+    /// The normalized right vector, perpendicular to forward and up.
+    pub fn right(&self) -> Vector3<f32> {
+        self.forward().cross(self.up).normalize()
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+pub struct CameraUniform {
+    pub view_proj: [[f32; 4]; 4],
+}
+
+impl CameraUniform {
+    pub fn new() -> Self {
+        use cgmath::SquareMatrix;
+        Self {
+            view_proj: Matrix4::identity().into(),
+        }
+    }
+
+    pub fn update_view_proj(&mut self, camera: &Camera) {
+        self.view_proj = camera.build_view_projection_matrix().into();
+    }
+}
+
+impl Default for CameraUniform {
+    fn default() -> Self {
+        Self::new()
+    }
+}
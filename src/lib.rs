@@ -1,12 +1,19 @@
+mod camera;
+mod hdr;
+mod renderer;
+mod texture;
+
 use std::{sync::Arc, time::Instant};
 
+use bytemuck::{Pod, Zeroable};
+use wgpu::util::DeviceExt;
 use wgpu::PresentMode;
 use winit::{
     application::ApplicationHandler,
     event::{ElementState, KeyEvent, MouseButton, WindowEvent},
     event_loop::ControlFlow,
     keyboard::{KeyCode, PhysicalKey},
-    window::{Window, WindowAttributes},
+    window::{Fullscreen, Window, WindowAttributes},
 };
 
 use pollster;
@@ -20,8 +27,145 @@ struct State<'a> {
     mouse_down: bool,
     micros: Instant,
     color_info: ColorInfo,
+    render_pipeline: wgpu::RenderPipeline,
+    shader: wgpu::ShaderModule,
+    pipeline_layout: wgpu::PipelineLayout,
+    topology: wgpu::PrimitiveTopology,
+    polygon_mode: wgpu::PolygonMode,
+    wireframe_supported: bool,
+    vertex_buffer: wgpu::Buffer,
+    index_buffer: wgpu::Buffer,
+    num_indices: u32,
+    #[allow(dead_code)]
+    diffuse_texture: texture::Texture,
+    diffuse_bind_group: wgpu::BindGroup,
+    camera: camera::Camera,
+    camera_uniform: camera::CameraUniform,
+    camera_buffers: Vec<wgpu::Buffer>,
+    camera_bind_groups: Vec<wgpu::BindGroup>,
+    last_mouse_pos: Option<(f64, f64)>,
+    depth_texture: texture::Texture,
+    renderer: renderer::Renderer,
+    mailbox_supported: bool,
+    hdr: hdr::HdrPipeline,
+    instance_buffer: wgpu::Buffer,
+    num_instances: u32,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+pub struct Vertex {
+    pub position: [f32; 3],
+    pub color: [f32; 3],
+    pub tex_coords: [f32; 2],
+}
+
+impl Vertex {
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Vertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 6]>() as wgpu::BufferAddress,
+                    shader_location: 2,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+            ],
+        }
+    }
+}
+
+/// A placed copy of the current mesh, used to build per-instance transforms.
+pub struct Instance {
+    pub position: cgmath::Vector3<f32>,
+    pub rotation: cgmath::Quaternion<f32>,
+}
+
+impl Instance {
+    fn to_raw(&self) -> InstanceRaw {
+        InstanceRaw {
+            model: (cgmath::Matrix4::from_translation(self.position)
+                * cgmath::Matrix4::from(self.rotation))
+            .into(),
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+pub struct InstanceRaw {
+    model: [[f32; 4]; 4],
 }
 
+impl InstanceRaw {
+    fn desc() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<InstanceRaw>() as wgpu::BufferAddress,
+            // Advance the instance data once per instance rather than per vertex.
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 5,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 4]>() as wgpu::BufferAddress,
+                    shader_location: 6,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 8]>() as wgpu::BufferAddress,
+                    shader_location: 7,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 12]>() as wgpu::BufferAddress,
+                    shader_location: 8,
+                    format: wgpu::VertexFormat::Float32x4,
+                },
+            ],
+        }
+    }
+}
+
+// A textured quad, used until the caller uploads its own geometry.
+const VERTICES: &[Vertex] = &[
+    Vertex {
+        position: [-0.5, 0.5, 0.0],
+        color: [1.0, 1.0, 1.0],
+        tex_coords: [0.0, 0.0],
+    },
+    Vertex {
+        position: [-0.5, -0.5, 0.0],
+        color: [1.0, 1.0, 1.0],
+        tex_coords: [0.0, 1.0],
+    },
+    Vertex {
+        position: [0.5, -0.5, 0.0],
+        color: [1.0, 1.0, 1.0],
+        tex_coords: [1.0, 1.0],
+    },
+    Vertex {
+        position: [0.5, 0.5, 0.0],
+        color: [1.0, 1.0, 1.0],
+        tex_coords: [1.0, 0.0],
+    },
+];
+
+const INDICES: &[u16] = &[0, 1, 2, 0, 2, 3];
+
 #[derive(Default)]
 struct ColorInfo {
     color: wgpu::Color,
@@ -53,10 +197,19 @@ impl<'a> State<'a> {
             .await
             .unwrap();
 
+        // Wireframe rendering needs POLYGON_MODE_LINE; request it only when the
+        // adapter supports it, and remember so the toggle can fail gracefully.
+        let wireframe_supported = adapter.features().contains(wgpu::Features::POLYGON_MODE_LINE);
+        let required_features = if wireframe_supported {
+            wgpu::Features::POLYGON_MODE_LINE
+        } else {
+            wgpu::Features::empty()
+        };
+
         let (device, queue) = adapter
             .request_device(
                 &wgpu::DeviceDescriptor {
-                    required_features: wgpu::Features::empty(),
+                    required_features,
                     required_limits: wgpu::Limits::default(),
                     label: None,
                 },
@@ -75,18 +228,180 @@ impl<'a> State<'a> {
             .find(|f| f.is_srgb())
             .copied()
             .unwrap_or(surface_caps.formats[0]);
+        // Mailbox gives the lowest latency but isn't supported everywhere;
+        // fall back to Fifo (vsync), which every adapter must support.
+        let mailbox_supported = surface_caps.present_modes.contains(&PresentMode::Mailbox);
+        let present_mode = if mailbox_supported {
+            PresentMode::Mailbox
+        } else {
+            PresentMode::Fifo
+        };
         let config = wgpu::SurfaceConfiguration {
             usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
             format: surface_format,
             width: size.width,
             height: size.height,
-            // present_mode: PresentMode::Fifo,
-            present_mode: PresentMode::Mailbox,
+            present_mode,
             alpha_mode: surface_caps.alpha_modes[0],
             view_formats: vec![],
             desired_maximum_frame_latency: 2,
         };
 
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("shader.wgsl").into()),
+        });
+
+        let texture_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("texture_bind_group_layout"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStages::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                        count: None,
+                    },
+                ],
+            });
+
+        // A generated checkerboard stands in until the caller supplies a texture.
+        let checker = image::DynamicImage::ImageRgba8(image::RgbaImage::from_fn(256, 256, |x, y| {
+            if (x / 32 + y / 32) % 2 == 0 {
+                image::Rgba([220, 220, 220, 255])
+            } else {
+                image::Rgba([60, 60, 60, 255])
+            }
+        }));
+        let diffuse_texture =
+            texture::Texture::from_image(&device, &queue, &checker, Some("diffuse_texture"));
+        let diffuse_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("diffuse_bind_group"),
+            layout: &texture_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&diffuse_texture.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&diffuse_texture.sampler),
+                },
+            ],
+        });
+
+        let camera = camera::Camera {
+            eye: (0.0, 1.0, 2.0).into(),
+            target: (0.0, 0.0, 0.0).into(),
+            up: cgmath::Vector3::unit_y(),
+            aspect: config.width as f32 / config.height as f32,
+            fovy: std::f32::consts::FRAC_PI_4,
+            znear: 0.1,
+            zfar: 100.0,
+        };
+        let mut camera_uniform = camera::CameraUniform::new();
+        camera_uniform.update_view_proj(&camera);
+        let camera_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("camera_bind_group_layout"),
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }],
+            });
+        // One camera buffer + bind group per frame in flight, so writing frame
+        // N's uniform doesn't stall on frame N-1's still-in-flight draw.
+        let camera_buffers: Vec<wgpu::Buffer> = (0..renderer::DEFAULT_FRAMES_IN_FLIGHT)
+            .map(|i| {
+                let label = format!("Camera Buffer {i}");
+                device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some(&label),
+                    contents: bytemuck::cast_slice(&[camera_uniform]),
+                    usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                })
+            })
+            .collect();
+        let camera_bind_groups: Vec<wgpu::BindGroup> = camera_buffers
+            .iter()
+            .map(|buffer| {
+                device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("camera_bind_group"),
+                    layout: &camera_bind_group_layout,
+                    entries: &[wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: buffer.as_entire_binding(),
+                    }],
+                })
+            })
+            .collect();
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Render Pipeline Layout"),
+            bind_group_layouts: &[&texture_bind_group_layout, &camera_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let topology = wgpu::PrimitiveTopology::TriangleList;
+        let polygon_mode = wgpu::PolygonMode::Fill;
+        // The scene renders into the HDR offscreen target, so its color
+        // target must match that format rather than the sRGB surface.
+        let render_pipeline = Self::create_render_pipeline(
+            &device,
+            &pipeline_layout,
+            &shader,
+            hdr::HDR_FORMAT,
+            topology,
+            polygon_mode,
+        );
+
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Vertex Buffer"),
+            contents: bytemuck::cast_slice(VERTICES),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Index Buffer"),
+            contents: bytemuck::cast_slice(INDICES),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+        let num_indices = INDICES.len() as u32;
+
+        // A single identity instance until the caller supplies their own.
+        let default_instance = Instance {
+            position: cgmath::Vector3::new(0.0, 0.0, 0.0),
+            rotation: cgmath::Quaternion::new(1.0, 0.0, 0.0, 0.0),
+        };
+        let instance_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Instance Buffer"),
+            contents: bytemuck::cast_slice(&[default_instance.to_raw()]),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let num_instances = 1;
+
+        let depth_texture =
+            texture::Texture::create_depth_texture(&device, &config, "depth_texture");
+
+        let mut renderer = renderer::Renderer::new();
+        renderer.add_pass(renderer::Phase::Opaque, Box::new(renderer::ScenePass));
+
+        let hdr = hdr::HdrPipeline::new(&device, &config);
+
         Self {
             surface,
             device,
@@ -96,15 +411,204 @@ impl<'a> State<'a> {
             mouse_down: false,
             micros: Instant::now(),
             color_info: ColorInfo::default(),
+            render_pipeline,
+            shader,
+            pipeline_layout,
+            topology,
+            polygon_mode,
+            wireframe_supported,
+            vertex_buffer,
+            index_buffer,
+            num_indices,
+            diffuse_texture,
+            diffuse_bind_group,
+            camera,
+            camera_uniform,
+            camera_buffers,
+            camera_bind_groups,
+            last_mouse_pos: None,
+            depth_texture,
+            renderer,
+            mailbox_supported,
+            hdr,
+            instance_buffer,
+            num_instances,
         }
     }
 
+    /// Upload per-instance transforms so the current mesh is drawn once per
+    /// instance in a single draw call. An empty slice is ignored, since
+    /// `create_buffer_init` rejects zero-sized contents.
+    pub fn set_instances(&mut self, instances: &[Instance]) {
+        if instances.is_empty() {
+            log::warn!("set_instances called with no instances; ignoring");
+            return;
+        }
+        let raw: Vec<InstanceRaw> = instances.iter().map(Instance::to_raw).collect();
+        self.instance_buffer = self
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Instance Buffer"),
+                contents: bytemuck::cast_slice(&raw),
+                usage: wgpu::BufferUsages::VERTEX,
+            });
+        self.num_instances = instances.len() as u32;
+    }
+
+    /// Brighten or darken the scene before tonemapping.
+    pub fn set_exposure(&mut self, exposure: f32) {
+        self.hdr.set_exposure(&self.queue, exposure);
+    }
+
+    /// Switch the surface's present mode and reconfigure. Reconfiguring is
+    /// skipped while the recorded size is zero; `resize` ignores zero sizes, so
+    /// a minimized window keeps its last non-zero size and is reconfigured at it.
+    pub fn set_present_mode(&mut self, mode: PresentMode) {
+        self.config.present_mode = mode;
+        if self.size.width > 0 && self.size.height > 0 {
+            self.surface.configure(&self.device, &self.config);
+        }
+    }
+
+    /// Toggle between vsync (`Fifo`) and low-latency (`Mailbox`) presentation.
+    /// Falls back to `Fifo` when the adapter doesn't support `Mailbox`.
+    pub fn toggle_present_mode(&mut self) {
+        let next = match self.config.present_mode {
+            PresentMode::Mailbox => PresentMode::Fifo,
+            _ if self.mailbox_supported => PresentMode::Mailbox,
+            _ => PresentMode::Fifo,
+        };
+        log::info!("present mode: {:?}", next);
+        self.set_present_mode(next);
+    }
+
+    /// Register an additional pass with the renderer so callers can compose
+    /// effects without editing [`State::render`] directly.
+    pub fn add_pass(&mut self, phase: renderer::Phase, pass: Box<dyn renderer::Pass>) {
+        self.renderer.add_pass(phase, pass);
+    }
+
+    /// Upload caller-provided geometry, replacing the default quad. Empty
+    /// vertex or index data is ignored, since `create_buffer_init` rejects
+    /// zero-sized contents.
+    pub fn set_mesh(&mut self, vertices: &[Vertex], indices: &[u16]) {
+        if vertices.is_empty() || indices.is_empty() {
+            log::warn!("set_mesh called with empty geometry; ignoring");
+            return;
+        }
+        self.vertex_buffer = self
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Vertex Buffer"),
+                contents: bytemuck::cast_slice(vertices),
+                usage: wgpu::BufferUsages::VERTEX,
+            });
+        self.index_buffer = self
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Index Buffer"),
+                contents: bytemuck::cast_slice(indices),
+                usage: wgpu::BufferUsages::INDEX,
+            });
+        self.num_indices = indices.len() as u32;
+    }
+
+    fn create_render_pipeline(
+        device: &wgpu::Device,
+        layout: &wgpu::PipelineLayout,
+        shader: &wgpu::ShaderModule,
+        format: wgpu::TextureFormat,
+        topology: wgpu::PrimitiveTopology,
+        polygon_mode: wgpu::PolygonMode,
+    ) -> wgpu::RenderPipeline {
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Render Pipeline"),
+            layout: Some(layout),
+            vertex: wgpu::VertexState {
+                module: shader,
+                entry_point: "vs_main",
+                buffers: &[Vertex::desc(), InstanceRaw::desc()],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: Default::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: Some(wgpu::Face::Back),
+                polygon_mode,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: texture::Texture::DEPTH_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+            cache: None,
+        })
+    }
+
+    /// Toggle between filled and wireframe rendering.
+    ///
+    /// `PolygonMode::Line` requires the `POLYGON_MODE_LINE` feature, which is
+    /// only enabled when the adapter supports it. The request is ignored (and
+    /// logged) on adapters that lack it, returning `false`, rather than letting
+    /// pipeline validation panic.
+    pub fn set_polygon_mode(&mut self, polygon_mode: wgpu::PolygonMode) -> bool {
+        if polygon_mode != wgpu::PolygonMode::Fill && !self.wireframe_supported {
+            log::warn!("polygon mode {:?} unsupported on this adapter", polygon_mode);
+            return false;
+        }
+        self.polygon_mode = polygon_mode;
+        self.rebuild_pipeline();
+        true
+    }
+
+    pub fn set_topology(&mut self, topology: wgpu::PrimitiveTopology) {
+        self.topology = topology;
+        self.rebuild_pipeline();
+    }
+
+    fn rebuild_pipeline(&mut self) {
+        self.render_pipeline = Self::create_render_pipeline(
+            &self.device,
+            &self.pipeline_layout,
+            &self.shader,
+            hdr::HDR_FORMAT,
+            self.topology,
+            self.polygon_mode,
+        );
+    }
+
     pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
         if new_size.width > 0 && new_size.height > 0 {
             self.size = new_size;
             self.config.width = new_size.width;
             self.config.height = new_size.height;
             self.surface.configure(&self.device, &self.config);
+            self.camera.aspect = new_size.width as f32 / new_size.height as f32;
+            self.depth_texture =
+                texture::Texture::create_depth_texture(&self.device, &self.config, "depth_texture");
+            self.hdr
+                .resize(&self.device, new_size.width, new_size.height);
         }
     }
 
@@ -113,43 +617,55 @@ impl<'a> State<'a> {
         let view = output
             .texture
             .create_view(&wgpu::TextureViewDescriptor::default());
-        let mut encoder = self
-            .device
-            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                label: Some("Render Encoder"),
-            });
 
-        {
-            let _render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("Render Pass"),
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(self.color_info.color),
-                        store: wgpu::StoreOp::Store,
-                    },
-                })],
-                depth_stencil_attachment: None,
-                occlusion_query_set: None,
-                timestamp_writes: None,
-            });
-        }
+        let frame_index = self.renderer.frame_index();
+        let frame = renderer::Frame {
+            view: self.hdr.view(),
+            depth_view: &self.depth_texture.view,
+            clear_color: self.color_info.color,
+            pipeline: &self.render_pipeline,
+            vertex_buffer: &self.vertex_buffer,
+            index_buffer: &self.index_buffer,
+            num_indices: self.num_indices,
+            instance_buffer: &self.instance_buffer,
+            num_instances: self.num_instances,
+            bind_groups: &[
+                &self.diffuse_bind_group,
+                &self.camera_bind_groups[frame_index],
+            ],
+        };
 
-        // submit will accept anything that implements IntoIter
-        self.queue.submit(std::iter::once(encoder.finish()));
+        self.renderer.render(&self.device, &self.queue, frame);
+        // Resolve the HDR target into the swapchain with tonemapping.
+        self.hdr.process(&self.device, &self.queue, &view);
         output.present();
 
         Ok(())
     }
 
     fn update(&mut self) {
+        self.camera_uniform.update_view_proj(&self.camera);
+        // Write into the current frame's camera buffer; the previous frame's
+        // buffer may still be read by an in-flight draw.
+        let frame_index = self.renderer.frame_index();
+        self.queue.write_buffer(
+            &self.camera_buffers[frame_index],
+            0,
+            bytemuck::cast_slice(&[self.camera_uniform]),
+        );
         self.micros = Instant::now();
     }
 
     fn input(&mut self, event: &WindowEvent) -> bool {
         match event {
             WindowEvent::CursorMoved { position, .. } => {
+                if self.mouse_down {
+                    if let Some((px, py)) = self.last_mouse_pos {
+                        self.rotate_camera(position.x - px, position.y - py);
+                    }
+                }
+                self.last_mouse_pos = Some((position.x, position.y));
+
                 let color_info = &mut self.color_info;
                 let b = color_info.color.b;
                 color_info.color = wgpu::Color {
@@ -170,9 +686,23 @@ impl<'a> State<'a> {
                     self.mouse_down = true;
                 } else {
                     self.mouse_down = false;
+                    self.last_mouse_pos = None;
                 }
                 true;
             }
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        physical_key: PhysicalKey::Code(code),
+                        state: ElementState::Pressed,
+                        ..
+                    },
+                ..
+            } => {
+                if self.move_camera(*code) {
+                    return true;
+                }
+            }
             _ => {}
         }
 
@@ -184,6 +714,47 @@ impl<'a> State<'a> {
         false
     }
 
+    /// Move the eye (and target) along the camera's forward/right axes in
+    /// response to WASD or the arrow keys, scaled by the frame's delta time.
+    fn move_camera(&mut self, code: KeyCode) -> bool {
+        let delta_time = self.micros.elapsed().as_nanos() as f32 / 1_000_000_000.0;
+        let speed = 5.0 * delta_time;
+        let forward = self.camera.forward();
+        let right = self.camera.right();
+
+        let offset = match code {
+            KeyCode::KeyW | KeyCode::ArrowUp => forward * speed,
+            KeyCode::KeyS | KeyCode::ArrowDown => -forward * speed,
+            KeyCode::KeyD | KeyCode::ArrowRight => right * speed,
+            KeyCode::KeyA | KeyCode::ArrowLeft => -right * speed,
+            _ => return false,
+        };
+
+        self.camera.eye += offset;
+        self.camera.target += offset;
+        true
+    }
+
+    /// Orbit the target around the eye while the left button is held. A mouse
+    /// delta is a displacement, not a rate, so sensitivity is a constant
+    /// radians-per-pixel factor rather than being scaled by delta time.
+    fn rotate_camera(&mut self, dx: f64, dy: f64) {
+        use cgmath::{InnerSpace, Rad, Rotation, Rotation3};
+
+        const SENSITIVITY: f32 = 0.005;
+        let yaw = Rad(-dx as f32 * SENSITIVITY);
+        let pitch = Rad(-dy as f32 * SENSITIVITY);
+
+        let dir = self.camera.target - self.camera.eye;
+        let radius = dir.magnitude();
+        let right = self.camera.right();
+
+        let rotation = cgmath::Quaternion::from_axis_angle(self.camera.up, yaw)
+            * cgmath::Quaternion::from_axis_angle(right, pitch);
+        let new_dir = rotation.rotate_vector(dir.normalize()) * radius;
+        self.camera.target = self.camera.eye + new_dir;
+    }
+
     fn cycle_blue(&mut self) {
         let delta_time = self.micros.elapsed().as_nanos() as f64 / 1_000_000_000.0;
         let delta = delta_time * 0.5;
@@ -257,6 +828,36 @@ impl ApplicationHandler for App<'_> {
                     event_loop.exit();
                 }
 
+                WindowEvent::KeyboardInput {
+                    event:
+                        KeyEvent {
+                            physical_key: PhysicalKey::Code(KeyCode::F11),
+                            state: ElementState::Pressed,
+                            ..
+                        },
+                    is_synthetic: false,
+                    ..
+                } => {
+                    let fullscreen = match window.fullscreen() {
+                        Some(_) => None,
+                        None => Some(Fullscreen::Borderless(None)),
+                    };
+                    window.set_fullscreen(fullscreen);
+                }
+
+                WindowEvent::KeyboardInput {
+                    event:
+                        KeyEvent {
+                            physical_key: PhysicalKey::Code(KeyCode::KeyV),
+                            state: ElementState::Pressed,
+                            ..
+                        },
+                    is_synthetic: false,
+                    ..
+                } => {
+                    state.toggle_present_mode();
+                }
+
                 WindowEvent::Resized(physical_size) => {
                     let state = self.state.as_mut().unwrap();
                     state.resize(physical_size);
@@ -0,0 +1,158 @@
+use multimap::MultiMap;
+
+/// Ordered rendering phases. Passes are recorded in variant-declaration order,
+/// so `Opaque` geometry is drawn before `Transparent` blending and any `Ui`
+/// overlay on top.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Phase {
+    Opaque,
+    Transparent,
+    Ui,
+}
+
+impl Phase {
+    /// All phases in the order they are recorded each frame.
+    pub const ALL: [Phase; 3] = [Phase::Opaque, Phase::Transparent, Phase::Ui];
+}
+
+/// The per-frame context handed to each [`Pass`] while it records.
+///
+/// It exposes the view to render into, the depth attachment, and the resources
+/// needed to draw the current mesh.
+pub struct Frame<'a> {
+    pub view: &'a wgpu::TextureView,
+    pub depth_view: &'a wgpu::TextureView,
+    pub clear_color: wgpu::Color,
+    pub pipeline: &'a wgpu::RenderPipeline,
+    pub vertex_buffer: &'a wgpu::Buffer,
+    pub index_buffer: &'a wgpu::Buffer,
+    pub num_indices: u32,
+    pub instance_buffer: &'a wgpu::Buffer,
+    pub num_instances: u32,
+    pub bind_groups: &'a [&'a wgpu::BindGroup],
+}
+
+/// A single unit of work recorded into the frame's command encoder.
+pub trait Pass {
+    fn record(&self, encoder: &mut wgpu::CommandEncoder, frame: &Frame<'_>);
+}
+
+/// The number of frames the CPU may be encoding ahead of the GPU. Callers size
+/// their rotating per-frame resource sets to this so encoding frame N uses
+/// different buffers than frame N-1, which may still be in flight on the GPU.
+pub const DEFAULT_FRAMES_IN_FLIGHT: usize = 2;
+
+/// A small render graph: passes grouped by [`Phase`] and replayed each frame
+/// into a single command encoder, then submitted as one command buffer. It
+/// tracks which frame-in-flight resource set is current via [`frame_index`].
+///
+/// [`frame_index`]: Renderer::frame_index
+pub struct Renderer {
+    passes: Vec<Box<dyn Pass>>,
+    phases: MultiMap<Phase, usize>,
+    frames_in_flight: usize,
+    frame_index: usize,
+}
+
+impl Renderer {
+    pub fn new() -> Self {
+        Self::with_frames_in_flight(DEFAULT_FRAMES_IN_FLIGHT)
+    }
+
+    pub fn with_frames_in_flight(frames_in_flight: usize) -> Self {
+        Self {
+            passes: Vec::new(),
+            phases: MultiMap::new(),
+            frames_in_flight: frames_in_flight.max(1),
+            frame_index: 0,
+        }
+    }
+
+    /// How many frames may be in flight; callers size their resource rings to
+    /// this.
+    pub fn frames_in_flight(&self) -> usize {
+        self.frames_in_flight
+    }
+
+    /// The resource set to use for the frame about to be recorded. Callers
+    /// index their per-frame buffers/bind groups with this before calling
+    /// [`render`], which advances it afterwards.
+    ///
+    /// [`render`]: Renderer::render
+    pub fn frame_index(&self) -> usize {
+        self.frame_index
+    }
+
+    /// Register a pass to run during `phase`. Passes within a phase run in the
+    /// order they were added.
+    pub fn add_pass(&mut self, phase: Phase, pass: Box<dyn Pass>) {
+        let index = self.passes.len();
+        self.passes.push(pass);
+        self.phases.insert(phase, index);
+    }
+
+    /// Record every registered pass, phase by phase, into one encoder, submit
+    /// it as a single command buffer, then rotate to the next frame's resource
+    /// set.
+    pub fn render(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, frame: Frame<'_>) {
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Render Encoder"),
+        });
+
+        for phase in Phase::ALL {
+            if let Some(indices) = self.phases.get_vec(&phase) {
+                for &index in indices {
+                    self.passes[index].record(&mut encoder, &frame);
+                }
+            }
+        }
+
+        queue.submit(std::iter::once(encoder.finish()));
+        self.frame_index = (self.frame_index + 1) % self.frames_in_flight;
+    }
+}
+
+impl Default for Renderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Clears the attachments and draws the current mesh. This is the built-in
+/// `Opaque` pass; users can register further passes for other phases.
+pub struct ScenePass;
+
+impl Pass for ScenePass {
+    fn record(&self, encoder: &mut wgpu::CommandEncoder, frame: &Frame<'_>) {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Render Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: frame.view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(frame.clear_color),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: frame.depth_view,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(1.0),
+                    store: wgpu::StoreOp::Store,
+                }),
+                stencil_ops: None,
+            }),
+            occlusion_query_set: None,
+            timestamp_writes: None,
+        });
+
+        render_pass.set_pipeline(frame.pipeline);
+        for (i, bind_group) in frame.bind_groups.iter().enumerate() {
+            render_pass.set_bind_group(i as u32, bind_group, &[]);
+        }
+        render_pass.set_vertex_buffer(0, frame.vertex_buffer.slice(..));
+        render_pass.set_vertex_buffer(1, frame.instance_buffer.slice(..));
+        render_pass.set_index_buffer(frame.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+        render_pass.draw_indexed(0..frame.num_indices, 0, 0..frame.num_instances);
+    }
+}